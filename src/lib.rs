@@ -0,0 +1,5 @@
+mod prime_tools;
+
+pub mod mod_arith;
+
+pub use prime_tools::{PrimeIterator, PrimeTools};
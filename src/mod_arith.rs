@@ -0,0 +1,87 @@
+// Modular arithmetic helpers shared by anything built on top of `PrimeTools`
+// (primitive roots, CRT-based puzzles, etc). Free functions rather than
+// methods since none of them need a `&mut self` to grow the prime list.
+
+pub fn mod_pow<T>(base: &T, exp: &T, modulus: &T) -> T
+where
+    T: num::Integer + Clone,
+{
+    let two = T::one() + T::one();
+
+    let mut result = T::one() % modulus.clone();
+    let mut base = base.clone() % modulus.clone();
+    let mut exp = exp.clone();
+
+    while exp > T::zero() {
+        if exp.is_odd() {
+            result = (result * base.clone()) % modulus.clone();
+        }
+        base = (base.clone() * base.clone()) % modulus.clone();
+        exp = exp / two.clone();
+    }
+
+    result
+}
+
+// Returns (g, x, y) such that a*x + b*y = g = gcd(a, b). The back-substitution
+// produces negative coefficients even for non-negative a, b, so this (and
+// everything built on it below) needs a signed T.
+pub fn ext_gcd<T>(a: &T, b: &T) -> (T, T, T)
+where
+    T: num::Integer + num::Signed + Clone,
+{
+    if b.is_zero() {
+        return (a.clone(), T::one(), T::zero());
+    }
+
+    let (g, x1, y1) = ext_gcd(b, &(a.clone() % b.clone()));
+    let q = a.clone() / b.clone();
+
+    (g, y1.clone(), x1 - q * y1)
+}
+
+pub fn mod_inverse<T>(a: &T, modulus: &T) -> Option<T>
+where
+    T: num::Integer + num::Signed + Clone,
+{
+    let (g, x, _) = ext_gcd(a, modulus);
+
+    if !g.is_one() {
+        None
+    } else {
+        Some(((x % modulus.clone()) + modulus.clone()) % modulus.clone())
+    }
+}
+
+// Merges congruences `x ≡ r_i (mod m_i)` pairwise, returning the combined
+// residue and the lcm of all moduli, or `None` if the system is
+// inconsistent (moduli share a factor the residues disagree on). An empty
+// slice also yields `None`.
+pub fn crt<T>(residues: &[(T, T)]) -> Option<(T, T)>
+where
+    T: num::Integer + num::Signed + Clone,
+{
+    let mut iter = residues.iter();
+    let (mut r, mut m) = iter.next()?.clone();
+
+    for (ri, mi) in iter {
+        let (g, p, _) = ext_gcd(&m, mi);
+
+        let diff = ri.clone() - r.clone();
+        if (diff.clone() % g.clone()) != T::zero() {
+            return None;
+        }
+
+        let mi_over_g = mi.clone() / g.clone();
+        let lcm = m.clone() * mi_over_g.clone();
+
+        let term = ((diff / g) * p) % mi_over_g;
+        r = (r + m.clone() * term) % lcm.clone();
+        if r < T::zero() {
+            r = r + lcm.clone();
+        }
+        m = lcm;
+    }
+
+    Some((r, m))
+}
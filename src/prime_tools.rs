@@ -1,12 +1,167 @@
 use std::collections::{BTreeMap, BTreeSet};
 
+use num::integer::Roots;
+use num::NumCast;
+
+// Below this gap (a count of new primes in `fill_n_primes`, a distance in
+// value past the last known prime in `fill_till_n`), growing the list
+// one-at-a-time via trial division against known primes is cheaper than
+// standing up a sieve.
+const SIEVE_THRESHOLD: usize = 1024;
+
+// Each segment's boolean array is kept around this size (32 KiB worth of
+// `bool`s), so it stays cache-resident while we mark off multiples.
+const SEGMENT_SIZE: usize = 32 * 1024;
+
+// Above this value, trial division (even against a pre-sieved base) takes
+// too long; switch to Miller-Rabin / Pollard's rho instead.
+const FAST_THRESHOLD: u64 = 1_000_000_000_000;
+
+// Deterministic Miller-Rabin witness set for all u64 inputs.
+const MILLER_RABIN_WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+fn mulmod(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 * b as u128) % m as u128) as u64
+}
+
+fn modpow(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    base %= m;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mulmod(result, base, m);
+        }
+        base = mulmod(base, base, m);
+        exp >>= 1;
+    }
+
+    result
+}
+
+fn miller_rabin(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+
+    for p in MILLER_RABIN_WITNESSES {
+        if n == p {
+            return true;
+        }
+        if n.is_multiple_of(p) {
+            return false;
+        }
+    }
+
+    let mut d = n - 1;
+    let mut s = 0;
+    while d.is_multiple_of(2) {
+        d /= 2;
+        s += 1;
+    }
+
+    'witness: for a in MILLER_RABIN_WITNESSES {
+        let mut x = modpow(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+
+        for _ in 0..s - 1 {
+            x = mulmod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+
+        return false;
+    }
+
+    true
+}
+
+// Pollard's rho with Brent's cycle detection: advance `y` in batches,
+// accumulating `|x-y|` as a running product mod `n` and taking its gcd with
+// `n` every (up to) 128 steps, only falling back to single stepping from the
+// start of the last chunk when that gcd comes out as `n` itself. Retries
+// with the next constant `c` (1, 2, 3, ...) if a run still degenerates to
+// the trivial factor `n`.
+fn pollard_rho(n: u64) -> u64 {
+    if n.is_multiple_of(2) {
+        return 2;
+    }
+
+    let mut c: u64 = 1;
+    loop {
+        let f = |x: u64| (mulmod(x, x, n) + c) % n;
+
+        let (mut x, mut y) = (2, 2);
+        let mut q = 1;
+        let mut g = 1;
+        let mut ys = y;
+        let mut batch = 1;
+
+        while g == 1 {
+            x = y;
+            for _ in 0..batch {
+                y = f(y);
+            }
+
+            let mut taken = 0;
+            while taken < batch && g == 1 {
+                ys = y;
+                let step = batch.min(batch - taken).min(128);
+                for _ in 0..step {
+                    y = f(y);
+                    q = mulmod(q, x.abs_diff(y), n);
+                }
+                g = num::integer::gcd(q, n);
+                taken += step;
+            }
+
+            batch *= 2;
+        }
+
+        if g == n {
+            loop {
+                ys = f(ys);
+                g = num::integer::gcd(x.abs_diff(ys), n);
+                if g > 1 {
+                    break;
+                }
+            }
+        }
+
+        if g != n {
+            return g;
+        }
+
+        c += 1;
+    }
+}
+
+fn pollard_factor(n: u64, factors: &mut BTreeMap<u64, usize>) {
+    if n == 1 {
+        return;
+    }
+
+    if miller_rabin(n) {
+        *factors.entry(n).or_insert(0) += 1;
+        return;
+    }
+
+    let d = pollard_rho(n);
+    pollard_factor(d, factors);
+    pollard_factor(n / d, factors);
+}
+
 pub struct PrimeTools<T> {
     primes: Vec<T>,
+    spf: Vec<usize>,
 }
 
 impl<T> PrimeTools<T>
 where
-    T: num::Integer + Clone + num::integer::Roots,
+    T: num::Integer + Clone + num::integer::Roots + NumCast,
     for<'a, 'b> &'a T: std::ops::Add<&'b T, Output = T> + std::ops::Rem<&'b T, Output = T>,
     for<'a> T: std::ops::Add<&'a T, Output = T> + std::ops::AddAssign<&'a T>,
 {
@@ -17,6 +172,7 @@ where
 
         PrimeTools {
             primes: vec![two, three],
+            spf: Vec::new(),
         }
     }
 
@@ -26,6 +182,10 @@ where
     }
 
     pub fn is_prime(&mut self, n: &T) -> bool {
+        if let Some(n) = n.to_u64().filter(|&n| n > FAST_THRESHOLD) {
+            return miller_rabin(n);
+        }
+
         if &self.primes[self.primes.len()] >= n {
             self.primes.contains(n)
         } else {
@@ -35,6 +195,14 @@ where
     }
 
     pub fn prime_factorization(&mut self, n: &T) -> BTreeMap<T, usize> {
+        if let Some(n) = n.to_u64().filter(|&n| n > FAST_THRESHOLD) {
+            return self
+                .factor_large(n)
+                .into_iter()
+                .map(|(p, exp)| (T::from(p).unwrap(), exp))
+                .collect();
+        }
+
         let mut m = n.clone();
 
         let mut sqrt_m = m.sqrt();
@@ -112,6 +280,105 @@ where
         count
     }
 
+    // Factors `n` by first peeling off small primes via the existing sieve
+    // (cheap, and common factors of "real" inputs), then handing whatever
+    // is left to Miller-Rabin / Pollard's rho so the search never degrades
+    // to trial division up to `sqrt(n)`.
+    fn factor_large(&mut self, n: u64) -> BTreeMap<u64, usize> {
+        let small_bound = T::from(100_000u64).unwrap();
+        self.fill_till_n(&small_bound);
+
+        let mut m = n;
+        let mut factors = BTreeMap::new();
+
+        for p in self.primes.iter().filter_map(|p| p.to_u64()) {
+            if p * p > m {
+                break;
+            }
+
+            if m.is_multiple_of(p) {
+                let mut exp = 0;
+                while m.is_multiple_of(p) {
+                    exp += 1;
+                    m /= p;
+                }
+                factors.insert(p, exp);
+            }
+        }
+
+        if m > 1 {
+            pollard_factor(m, &mut factors);
+        }
+
+        factors
+    }
+
+    pub fn euler_totient(&mut self, n: &T) -> T {
+        let mut result = T::one();
+
+        for (prime, exp) in self.prime_factorization(n).into_iter() {
+            result = result * num::pow(prime.clone(), exp - 1) * (prime - T::one());
+        }
+
+        result
+    }
+
+    pub fn mobius(&mut self, n: &T) -> i8 {
+        let prime_factors = self.prime_factorization(n);
+
+        if prime_factors.values().any(|&exp| exp > 1) {
+            return 0;
+        }
+
+        if prime_factors.len().is_multiple_of(2) {
+            1
+        } else {
+            -1
+        }
+    }
+
+    pub fn multiplicative<F>(&mut self, n: &T, f: F) -> T
+    where
+        F: Fn(&T, usize) -> T,
+    {
+        let mut result = T::one();
+
+        for (prime, exp) in self.prime_factorization(n).into_iter() {
+            result = result * f(&prime, exp);
+        }
+
+        result
+    }
+
+    // Factors p-1 and checks candidate generators g = 2, 3, ... against
+    // each prime factor q of p-1: g is a primitive root mod p iff
+    // g^((p-1)/q) != 1 (mod p) for every such q.
+    pub fn primitive_root(&mut self, p: &T) -> Option<T> {
+        let two = T::one() + T::one();
+        if p == &two {
+            return Some(T::one());
+        }
+
+        let totient = p.clone() - T::one();
+        let prime_factors: Vec<T> = self.prime_factorization(&totient).into_keys().collect();
+
+        let mut g = two;
+        while &g < p {
+            let is_root = prime_factors.iter().all(|q| {
+                let exp = totient.clone() / q.clone();
+                crate::mod_arith::mod_pow(&g, &exp, p) != T::one()
+            });
+
+            if is_root {
+                return Some(g);
+            }
+
+            g += &T::one();
+        }
+
+        None
+    }
+
     fn add_prime(&mut self) {
         let two = T::one() + T::one();
         let mut next_prime = self.primes.last().unwrap() + &two;
@@ -128,25 +395,214 @@ where
     }
 
     fn fill_n_primes(&mut self, n: usize) {
-        while self.primes.len() < n {
+        if n >= self.primes.len() + SIEVE_THRESHOLD {
+            let upper = Self::nth_prime_bound(n);
+            self.sieve_until(&upper);
+        }
+
+        while self.primes.len() <= n {
             self.add_prime();
         }
     }
 
     fn fill_till_n(&mut self, n: &T) {
+        let gap_is_large = match (self.primes.last().unwrap().to_usize(), n.to_usize()) {
+            (Some(last), Some(n)) => n >= last + SIEVE_THRESHOLD,
+            _ => true,
+        };
+
+        if gap_is_large {
+            self.sieve_until(n);
+        }
+
         while self.primes.last().unwrap() < n {
             self.add_prime();
         }
     }
 
+    // Rough upper bound on the n-th prime (0-indexed), from the standard
+    // p_n < n * (ln n + ln ln n) estimate for n >= 6, used to size the
+    // sieve so `fill_n_primes` only needs to run it once.
+    fn nth_prime_bound(n: usize) -> T {
+        let n = n + 1;
+        let bound = if n < 6 {
+            15.0
+        } else {
+            let n = n as f64;
+            n * (n.ln() + n.ln().ln())
+        };
+
+        T::from(bound.ceil()).unwrap()
+    }
+
+    // Segmented Sieve of Eratosthenes: sieves a base block up to `sqrt(upper)`
+    // with a classic boolean array, then sweeps `[sqrt(upper), upper]` in
+    // fixed-size segments, marking each segment's composites from the base
+    // primes. Replaces `self.primes` with the result, which is far cheaper
+    // than growing the list one prime at a time via `add_prime` once the
+    // gap to fill is large.
+    fn sieve_until(&mut self, upper: &T) {
+        let upper: usize = match upper.to_usize() {
+            Some(upper) => upper,
+            None => return,
+        };
+
+        if upper < self.primes.last().unwrap().to_usize().unwrap_or(0) {
+            return;
+        }
+
+        let sqrt_upper = upper.sqrt() + 1;
+
+        let mut base_composite = vec![false; sqrt_upper + 1];
+        let mut primes = Vec::new();
+
+        for p in 2..=sqrt_upper {
+            if !base_composite[p] {
+                primes.push(p);
+
+                let mut multiple = p * p;
+                while multiple <= sqrt_upper {
+                    base_composite[multiple] = true;
+                    multiple += p;
+                }
+            }
+        }
+
+        let base_primes = primes.clone();
+
+        let mut lo = sqrt_upper + 1;
+        while lo <= upper {
+            let hi = (lo + SEGMENT_SIZE - 1).min(upper);
+            let mut segment_composite = vec![false; hi - lo + 1];
+
+            for &p in &base_primes {
+                let start = (p * p).max(lo.div_ceil(p) * p);
+
+                let mut multiple = start;
+                while multiple <= hi {
+                    segment_composite[multiple - lo] = true;
+                    multiple += p;
+                }
+            }
+
+            for (offset, &is_composite) in segment_composite.iter().enumerate() {
+                if !is_composite {
+                    primes.push(lo + offset);
+                }
+            }
+
+            lo = hi + 1;
+        }
+
+        self.primes = primes
+            .into_iter()
+            .map(|p| T::from(p).unwrap())
+            .collect();
+    }
+
     pub fn iter_primes(&mut self) -> PrimeIterator<'_, T> {
         PrimeIterator { pt: self, pos: 0 }
     }
+
+    // Linear sieve: each composite is marked exactly once, by its smallest
+    // prime factor, via the prime found so far that is `<= spf[i]`. Makes
+    // `factor_fast` below an O(log n) lookup-and-divide instead of a fresh
+    // trial division per query.
+    pub fn build_spf(&mut self, upper: usize) {
+        let mut spf = vec![0usize; upper + 1];
+        let mut primes = Vec::new();
+
+        for i in 2..=upper {
+            if spf[i] == 0 {
+                spf[i] = i;
+                primes.push(i);
+            }
+
+            for &p in &primes {
+                if p > spf[i] || i * p > upper {
+                    break;
+                }
+                spf[i * p] = p;
+            }
+        }
+
+        self.spf = spf;
+    }
+
+    // Factors any `n <= upper` passed to `build_spf` in O(log n) by
+    // repeatedly dividing out its smallest prime factor.
+    pub fn factor_fast(&self, n: usize) -> BTreeMap<usize, usize> {
+        let mut n = n;
+        let mut factors = BTreeMap::new();
+
+        while n > 1 {
+            let p = self.spf[n];
+            *factors.entry(p).or_insert(0) += 1;
+            n /= p;
+        }
+
+        factors
+    }
+
+    // Counts primes <= n without materializing any of them, via the
+    // Lucy_Hedgehog / Meissel recurrence: S[v] starts as "integers in
+    // 2..=v" and each prime p sieves itself out of every S[v] for v >= p^2.
+    // Since S is only ever evaluated at the O(sqrt(n)) distinct values of
+    // n/i, it's stored as two arrays: `small[i] = S[i]` and
+    // `large[i] = S[n/i]`, both indexed up to sqrt(n).
+    pub fn prime_count(&mut self, n: &T) -> T {
+        let n: usize = n
+            .to_usize()
+            .expect("prime_count: n does not fit in a usize");
+
+        if n < 2 {
+            return T::zero();
+        }
+
+        let sqrt_n: usize = n.sqrt();
+
+        let mut small: Vec<usize> = (0..=sqrt_n).map(|i| i.saturating_sub(1)).collect();
+        let mut large: Vec<usize> = std::iter::once(0)
+            .chain((1..=sqrt_n).map(|i| n / i - 1))
+            .collect();
+
+        let get = |small: &[usize], large: &[usize], v: usize| -> usize {
+            if v <= sqrt_n {
+                small[v]
+            } else {
+                large[n / v]
+            }
+        };
+
+        for p in 2..=sqrt_n {
+            if small[p] == small[p - 1] {
+                continue;
+            }
+
+            let count_below_p = small[p - 1];
+            let p2 = p * p;
+
+            let i_max = (n / p2).min(sqrt_n);
+            for i in 1..=i_max {
+                let v = n / i;
+                let d = get(&small, &large, v / p);
+                large[i] -= d - count_below_p;
+            }
+
+            for v in (p2..=sqrt_n).rev() {
+                let d = get(&small, &large, v / p);
+                small[v] -= d - count_below_p;
+            }
+        }
+
+        let count = get(&small, &large, n);
+        T::from(count).unwrap()
+    }
 }
 
 impl<T> Default for PrimeTools<T>
 where
-    T: num::Integer + Clone + num::integer::Roots,
+    T: num::Integer + Clone + num::integer::Roots + NumCast,
     for<'a, 'b> &'a T: std::ops::Add<&'b T, Output = T> + std::ops::Rem<&'b T, Output = T>,
     for<'a> T: std::ops::Add<&'a T, Output = T> + std::ops::AddAssign<&'a T>,
 {
@@ -163,7 +619,7 @@ pub struct PrimeIterator<'pt, T> {
 // GAT isn't stabilized yet, so T instead of &T
 impl<'pt, T> Iterator for PrimeIterator<'pt, T>
 where
-    T: num::Integer + Clone + num::integer::Roots,
+    T: num::Integer + Clone + num::integer::Roots + NumCast,
     for<'a, 'b> &'a T: std::ops::Add<&'b T, Output = T> + std::ops::Rem<&'b T, Output = T>,
     for<'a> T: std::ops::Add<&'a T, Output = T> + std::ops::AddAssign<&'a T>,
 {